@@ -0,0 +1,149 @@
+//! Асинхронный вариант канала связи на базе `tokio-serial`, позволяющий
+//! опрашивать несколько каналов одновременно вместо одного за другим.
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio_serial::{SerialPort, SerialPortBuilderExt};
+
+/// # Типаж асинхронного канала связи
+///
+/// Зеркало `ILinkChannel`, но с неблокирующими методами обмена. Существует
+/// параллельно блокирующему типажу ради обратной совместимости.
+#[async_trait]
+pub trait IAsyncLinkChannel {
+    /// Конструктор
+    fn new() -> Self
+    where
+        Self: Sized;
+    /// Настройка канала связи
+    async fn reconf(&mut self);
+    /// Отправить данные
+    async fn send(&mut self, data: &[u8]);
+    /// Прочитать данные
+    async fn read(&mut self) -> Vec<u8>;
+}
+
+/// Асинхронный последовательный канал поверх `tokio_serial::SerialStream`.
+pub struct AsyncSerialChannel {
+    port: Option<tokio_serial::SerialStream>,
+    port_name: String,
+    baud_rate: u32,
+}
+
+impl AsyncSerialChannel {
+    /// Конструктор с явным портом и скоростью
+    pub fn with_address(port_name: &str, baud_rate: u32) -> AsyncSerialChannel {
+        AsyncSerialChannel {
+            port: None,
+            port_name: port_name.to_owned(),
+            baud_rate,
+        }
+    }
+}
+
+#[async_trait]
+impl IAsyncLinkChannel for AsyncSerialChannel {
+    fn new() -> AsyncSerialChannel {
+        AsyncSerialChannel::with_address("COM1", 9600)
+    }
+
+    async fn reconf(&mut self) {
+        let mut port = tokio_serial::new(&self.port_name, self.baud_rate)
+            .open_native_async()
+            .unwrap();
+        port.set_timeout(Duration::from_secs(1)).unwrap();
+        self.port = Some(port);
+    }
+
+    async fn send(&mut self, data: &[u8]) {
+        if let Some(ref mut port) = self.port {
+            port.write_all(data).await.unwrap();
+        }
+    }
+
+    async fn read(&mut self) -> Vec<u8> {
+        let mut result: Vec<u8> = vec![0u8; 255];
+
+        if let Some(ref mut port) = self.port {
+            let reading = port.read(&mut result[..]).await.unwrap();
+            result.truncate(reading);
+        };
+        result
+    }
+}
+
+/// Опросить несколько асинхронных каналов одновременно: настроить, отправить
+/// `request` и прочитать ответ на каждом — не дожидаясь ответа предыдущего.
+pub async fn poll_concurrently(
+    channels: &[Arc<AsyncMutex<dyn IAsyncLinkChannel + Send>>],
+    request: &[u8],
+) -> Vec<Vec<u8>> {
+    join_all(channels.iter().map(|channel| async move {
+        let mut channel = channel.lock().await;
+        channel.reconf().await;
+        channel.send(request).await;
+        channel.read().await
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    struct MockAsyncChannel {
+        reply: Vec<u8>,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl IAsyncLinkChannel for MockAsyncChannel {
+        fn new() -> Self {
+            MockAsyncChannel {
+                reply: vec![],
+                delay: Duration::from_millis(0),
+            }
+        }
+
+        async fn reconf(&mut self) {}
+
+        async fn send(&mut self, _data: &[u8]) {}
+
+        async fn read(&mut self) -> Vec<u8> {
+            tokio::time::sleep(self.delay).await;
+            self.reply.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn polls_several_channels_concurrently() {
+        let delay = Duration::from_millis(50);
+        let channels: Vec<Arc<AsyncMutex<dyn IAsyncLinkChannel + Send>>> = (0..4)
+            .map(|i| {
+                let channel: Arc<AsyncMutex<dyn IAsyncLinkChannel + Send>> =
+                    Arc::new(AsyncMutex::new(MockAsyncChannel {
+                        reply: vec![i as u8],
+                        delay,
+                    }));
+                channel
+            })
+            .collect();
+
+        let started = Instant::now();
+        let responses = poll_concurrently(&channels, &[0x00]).await;
+        let elapsed = started.elapsed();
+
+        assert_eq!(responses.len(), 4);
+        for (i, response) in responses.iter().enumerate() {
+            assert_eq!(response, &vec![i as u8]);
+        }
+        // Если бы каналы опрашивались по очереди, на четыре канала ушло бы
+        // не меньше 4 * delay; совместный опрос укладывается в пару delay.
+        assert!(elapsed < delay * 3, "elapsed {:?} looks sequential, not concurrent", elapsed);
+    }
+}
@@ -0,0 +1,82 @@
+//! Подсистема контрольных сумм кадра — каждый счётчик объявляет свою через `ICounter::checksum()`.
+
+/// Алгоритм расчёта контрольной суммы кадра.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumMode {
+    /// CRC16/Modbus — используется приборами серии Меркурий 230
+    Crc16Modbus,
+}
+
+/// # Типаж вычисления и проверки контрольной суммы кадра
+pub trait Checksum {
+    /// Какой алгоритм реализует данный экземпляр
+    fn mode(&self) -> ChecksumMode;
+    /// Посчитать контрольную сумму для полезной нагрузки
+    fn compute(&self, payload: &[u8]) -> Vec<u8>;
+    /// Проверить, что кадр оканчивается верной контрольной суммой
+    fn validate(&self, frame: &[u8]) -> bool;
+}
+
+/// Реализация CRC16/Modbus.
+pub struct Crc16Modbus;
+
+impl Crc16Modbus {
+    fn crc(payload: &[u8]) -> u16 {
+        let mut crc: u16 = 0xFFFF;
+        for &byte in payload {
+            crc ^= byte as u16;
+            for _ in 0..8 {
+                if crc & 1 != 0 {
+                    crc = (crc >> 1) ^ 0xA001;
+                } else {
+                    crc >>= 1;
+                }
+            }
+        }
+        crc
+    }
+}
+
+impl Checksum for Crc16Modbus {
+    fn mode(&self) -> ChecksumMode {
+        ChecksumMode::Crc16Modbus
+    }
+
+    fn compute(&self, payload: &[u8]) -> Vec<u8> {
+        let crc = Self::crc(payload);
+        vec![(crc & 0xFF) as u8, (crc >> 8) as u8]
+    }
+
+    fn validate(&self, frame: &[u8]) -> bool {
+        if frame.len() < 2 {
+            return false;
+        }
+        let (payload, tail) = frame.split_at(frame.len() - 2);
+        self.compute(payload) == tail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_matches_known_good_modbus_frame() {
+        // Хрестоматийный пример запроса Modbus (чтение регистров), см.
+        // спецификацию Modbus — CRC передаётся младшим байтом вперёд.
+        let payload = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A];
+        assert_eq!(Crc16Modbus.compute(&payload), vec![0xC5, 0xCD]);
+    }
+
+    #[test]
+    fn validate_accepts_frame_with_correct_trailing_crc() {
+        let frame = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0xC5, 0xCD];
+        assert!(Crc16Modbus.validate(&frame));
+    }
+
+    #[test]
+    fn validate_rejects_frame_with_wrong_crc() {
+        let frame = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00];
+        assert!(!Crc16Modbus.validate(&frame));
+    }
+}
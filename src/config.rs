@@ -0,0 +1,163 @@
+//! Построение топологии опроса (каналы связи + дочерние счётчики) из JSON вида:
+//!
+//! ```json
+//! [
+//!   {
+//!     "type": "serial",
+//!     "port_name": "COM1",
+//!     "baud_rate": 9600,
+//!     "counters": [
+//!       { "type_name": "IMercury230", "address": 1, "name": "Ввод №1" }
+//!     ]
+//!   },
+//!   {
+//!     "type": "tcp",
+//!     "host": "192.168.1.10",
+//!     "port": 502,
+//!     "counters": [
+//!       { "type_name": "IMercury230", "address": 2 }
+//!     ]
+//!   }
+//! ]
+//! ```
+//!
+//! Счётчики создаются через фабрику, зарегистрированную по `ICounter::type_name()`.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::tcp_channel::TcpChannel;
+use crate::{CounterList, IChannelHandle, ICounter, IMercury230, SerialChannel};
+
+/// Описание канала связи и его дочерних счётчиков.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ChannelConfig {
+    Serial {
+        port_name: String,
+        baud_rate: u32,
+        #[serde(default)]
+        counters: Vec<CounterConfig>,
+    },
+    Tcp {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        counters: Vec<CounterConfig>,
+    },
+}
+
+/// Описание одного счётчика на канале.
+#[derive(Debug, Deserialize)]
+struct CounterConfig {
+    type_name: String,
+    address: u8,
+    name: Option<String>,
+    serial: Option<String>,
+    verification_interval_secs: Option<u64>,
+}
+
+/// Фабрика: строит конкретный счётчик по его `type_name`.
+type CounterFactory = fn(IChannelHandle, &CounterConfig) -> Arc<Mutex<dyn ICounter + Send>>;
+
+/// Реестр известных типов счётчиков, ключ — `ICounter::type_name()`.
+fn counter_registry() -> HashMap<&'static str, CounterFactory> {
+    let mut registry: HashMap<&'static str, CounterFactory> = HashMap::new();
+    registry.insert(IMercury230::type_name(), |channel, cfg| {
+        let mut counter = IMercury230::new(channel);
+        counter.address = cfg.address;
+        counter._name = cfg.name.clone();
+        counter._serial = cfg.serial.clone();
+        if let Some(secs) = cfg.verification_interval_secs {
+            let _ = counter.set_verification_interval(Duration::from_secs(secs));
+        }
+        Arc::new(Mutex::new(counter))
+    });
+    registry
+}
+
+/// Загрузить JSON-документ и построить из него готовый `CounterList`.
+pub fn load_counter_list(path: &str) -> CounterList {
+    let raw = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!(
+            "Не удалось открыть файл конфигурации '{}': {}. Формат описан в документации модуля config.",
+            path, err
+        );
+        std::process::exit(1);
+    });
+    let channels: Vec<ChannelConfig> = serde_json::from_str(&raw).unwrap_or_else(|err| {
+        eprintln!("Файл конфигурации '{}' не является корректным JSON: {}", path, err);
+        std::process::exit(1);
+    });
+    let registry = counter_registry();
+
+    let mut list = CounterList::new();
+    for channel_config in channels {
+        let (channel, counters): (IChannelHandle, Vec<CounterConfig>) = match channel_config {
+            ChannelConfig::Serial {
+                port_name,
+                baud_rate,
+                counters,
+            } => {
+                let channel = SerialChannel::with_address(
+                    &port_name,
+                    serial::BaudRate::from_speed(baud_rate as usize),
+                );
+                (Arc::new(Mutex::new(channel)), counters)
+            }
+            ChannelConfig::Tcp {
+                host,
+                port,
+                counters,
+            } => (Arc::new(Mutex::new(TcpChannel::with_address(&host, port))), counters),
+        };
+
+        for counter_config in &counters {
+            if let Some(factory) = registry.get(counter_config.type_name.as_str()) {
+                list.counters.push(factory(channel.clone(), counter_config));
+            }
+        }
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn builds_counter_list_from_json() {
+        let json = r#"[
+            {
+                "type": "serial",
+                "port_name": "COM1",
+                "baud_rate": 9600,
+                "counters": [
+                    { "type_name": "IMercury230", "address": 1, "name": "Ввод №1" }
+                ]
+            },
+            {
+                "type": "tcp",
+                "host": "192.168.1.10",
+                "port": 502,
+                "counters": [
+                    { "type_name": "IMercury230", "address": 2 }
+                ]
+            }
+        ]"#;
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("poll_engine_config_test_{:p}.json", json.as_ptr()));
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(json.as_bytes()).unwrap();
+
+        let list = load_counter_list(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(list.counters.len(), 2);
+    }
+}
@@ -1,22 +1,39 @@
 extern crate byteorder;
-extern crate crc;
+extern crate serde;
+extern crate serde_json;
 extern crate serial;
 extern crate uuid;
 
+mod async_channel;
+mod checksum;
+mod config;
+mod scheduler;
+mod tcp_channel;
+
+use checksum::{Checksum, Crc16Modbus};
+#[allow(unused_imports)]
+use config::load_counter_list;
+#[allow(unused_imports)]
+use tcp_channel::TcpChannel;
 use byteorder::{BigEndian, ReadBytesExt};
-use crc::crc32;
+use scheduler::Scheduler;
 use serial::prelude::*;
-use std::cell::RefCell;
 #[allow(unused_imports)]
 use std::io::prelude::*;
 use std::io::Cursor;
 use std::iter::Iterator;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use uuid::Uuid;
 
 /// Тип соответствует представлению последовательного порта
-type ISerialPort = Rc<RefCell<SerialPort>>;
+type ISerialPort = Arc<Mutex<dyn SerialPort>>;
+
+/// Разделяемый между счётчиками и планировщиком канал связи
+type IChannelHandle = Arc<Mutex<dyn ILinkChannel + Send>>;
+
+/// Разделяемый между списком и планировщиком счётчик
+type ICounterHandle = Arc<Mutex<dyn ICounter + Send>>;
 
 /// Тип представляет из себя UUID
 type IGUID = String;
@@ -41,7 +58,7 @@ trait ILinkChannel {
 
 trait ICounter {
     /// Конструктор
-    fn new(channel: Rc<RefCell<ILinkChannel>>) -> Self
+    fn new(channel: IChannelHandle) -> Self
     where
         Self: Sized;
     /// Уникальный GUID устройства
@@ -69,7 +86,9 @@ trait ICounter {
     /// Установим интервал между поверками
     fn set_verification_interval(&mut self, interval: Duration) -> std::io::Result<()>;
     /// Вернуть канал связи
-    fn parent(&self) -> Rc<RefCell<ILinkChannel>>;
+    fn parent(&self) -> IChannelHandle;
+    /// Контрольная сумма, которой счётчик защищает свои кадры
+    fn checksum(&self) -> Box<dyn Checksum>;
 }
 
 trait IElectroCounter: ICounter {
@@ -96,9 +115,14 @@ struct SerialChannel {
     port: Option<ISerialPort>,
     port_name: String,
     baud_rate: serial::BaudRate,
-    _child: Vec<Rc<RefCell<ICounter>>>,
+    _child: Vec<ICounterHandle>,
 }
 
+// `serial::SerialPort` не объявлен `Send`, хотя под ним лишь файловый
+// дескриптор ОС без привязки к потоку; доступ к нему и так всегда идёт
+// через `Mutex`, так что перемещение канала между потоками планировщика безопасно.
+unsafe impl Send for SerialChannel {}
+
 impl ILinkChannel for SerialChannel {
     fn new() -> SerialChannel {
         SerialChannel {
@@ -110,7 +134,7 @@ impl ILinkChannel for SerialChannel {
     }
 
     fn reconf(&mut self) {
-        self.port = Some(Rc::new(RefCell::new(
+        self.port = Some(Arc::new(Mutex::new(
             serial::open(&self.port_name).unwrap(),
         )));
 
@@ -122,8 +146,9 @@ impl ILinkChannel for SerialChannel {
             flow_control: serial::FlowNone,
         };
         if let Some(ref mut port) = self.port {
-            let _ = port.borrow_mut().configure(&settings).unwrap();
-            port.borrow_mut()
+            let _ = port.lock().unwrap().configure(&settings).unwrap();
+            port.lock()
+                .unwrap()
                 .set_timeout(Duration::from_secs(1))
                 .unwrap();
         }
@@ -131,7 +156,7 @@ impl ILinkChannel for SerialChannel {
 
     fn send(&mut self, data: &Vec<u8>) {
         if let Some(ref mut port) = self.port {
-            let _ = port.borrow_mut().write(&data[..]).unwrap();
+            let _ = port.lock().unwrap().write(&data[..]).unwrap();
         }
     }
 
@@ -139,16 +164,28 @@ impl ILinkChannel for SerialChannel {
         let mut result: Vec<u8> = (0..255).collect();
 
         if let Some(ref mut port) = self.port {
-            let reading = port.borrow_mut().read(&mut result[..]).unwrap();
+            let reading = port.lock().unwrap().read(&mut result[..]).unwrap();
             result.truncate(reading);
         };
         result
     }
 }
 
+impl SerialChannel {
+    /// Конструктор с явными параметрами порта (используется конфигурацией)
+    fn with_address(port_name: &str, baud_rate: serial::BaudRate) -> SerialChannel {
+        SerialChannel {
+            port: None,
+            port_name: port_name.to_owned(),
+            baud_rate,
+            _child: vec![],
+        }
+    }
+}
+
 #[derive(Default)]
 struct CounterList {
-    counters: Vec<Box<RefCell<dyn ICounter>>>,
+    counters: Vec<ICounterHandle>,
 }
 
 impl CounterList {
@@ -156,31 +193,86 @@ impl CounterList {
         Self::default()
     }
 
-    // Производим обмен со всеми счётчиками
-    fn processing(&mut self) {
-        for mut counter in &mut self.counters {
-            if let Ok(mut counter_borrowed) = counter.try_borrow_mut() {
-                counter_borrowed.communicate();
-            }
-        }
+    // Производим обмен со всеми счётчиками, раскладывая независимые каналы
+    // по рабочим потокам планировщика; возвращаем по одному результату на
+    // каждый счётчик. Порядок результатов следует группам каналов, а не
+    // порядку `counters`, и сам результат не называет ни счётчик, ни канал
+    fn processing(&mut self) -> Vec<scheduler::PollOutcome> {
+        let concurrency = scheduler::default_concurrency(2);
+        Scheduler::run(self.counters.clone(), concurrency)
     }
 }
 
+/// Параметр, который надо прочитать у счётчика за один сеанс обмена
+enum ParamRequest {
+    /// Расход (уже существовавший запрос, группа `0x05`)
+    Consumption,
+    /// Активная энергия по фазе (1..=3), группа `0x08`, массив `0x11`
+    ActiveEnergy(u8),
+    /// Реактивная энергия по фазе (1..=3), группа `0x08`, массив `0x12`
+    ReactiveEnergy(u8),
+    /// Действующее значение напряжения по фазе (1..=3), группа `0x08`, массив `0x16`
+    Voltage(u8),
+    /// Частота сети, группа `0x08`, массив `0x16`, селектор `0x40`
+    Frequency,
+}
+
+/// Разобрать 3-байтное число с фиксированной запятой из ответа Меркурия 230
+fn parse_fixed_point(response: &[u8]) -> Option<f64> {
+    if response.len() < 5 {
+        return None;
+    }
+    let raw = ((response[2] as u32) << 16) | ((response[3] as u32) << 8) | response[4] as u32;
+    Some(raw as f64 / 1000.0)
+}
+
 struct IMercury230 {
-    _parent: Rc<RefCell<ILinkChannel>>,
+    _parent: IChannelHandle,
     _consumption: IConsumption,
+    _active_energy: [Option<f64>; 3],
+    _reactive_energy: [Option<f64>; 3],
+    _voltage: [Option<f32>; 3],
+    _frequency: Option<i32>,
     _serial: Option<String>,
     _name: Option<String>,
     guid: IGUID,
     address: u8,
 }
 
+impl IMercury230 {
+    /// Запрос на открытие сеанса связи (пароль уровня USER по умолчанию)
+    fn open_session_request(&self) -> Vec<u8> {
+        let mut request = vec![self.address, 0x01, 0x01, 0, 0, 0, 0, 0, 0];
+        let mut crc = self.checksum().compute(&request[..]);
+        request.append(&mut crc);
+        request
+    }
+
+    /// Собрать кадр запроса под конкретный параметр
+    fn param_request(&self, kind: &ParamRequest) -> Vec<u8> {
+        let mut request = match *kind {
+            ParamRequest::Consumption => vec![self.address, 0x05, 0x00, 0x01],
+            ParamRequest::ActiveEnergy(phase) => vec![self.address, 0x08, 0x11, phase],
+            ParamRequest::ReactiveEnergy(phase) => vec![self.address, 0x08, 0x12, phase],
+            ParamRequest::Voltage(phase) => vec![self.address, 0x08, 0x16, 0x10 + phase],
+            ParamRequest::Frequency => vec![self.address, 0x08, 0x16, 0x40],
+        };
+        let mut crc = self.checksum().compute(&request[..]);
+        request.append(&mut crc);
+        request
+    }
+}
+
 impl ICounter for IMercury230 {
     // Конструктор
-    fn new(channel: Rc<RefCell<ILinkChannel>>) -> Self {
+    fn new(channel: IChannelHandle) -> Self {
         IMercury230 {
             _parent: channel,
             _consumption: 0.0,
+            _active_energy: [None; 3],
+            _reactive_energy: [None; 3],
+            _voltage: [None; 3],
+            _frequency: None,
             _serial: None,
             _name: None,
             guid: String::new(),
@@ -200,30 +292,62 @@ impl ICounter for IMercury230 {
     fn communicate(&mut self) {
         // Получаем канал связи для работы
         let parent = self.parent();
-        let mut parent_borrowed = parent.borrow_mut();
+        let mut parent_borrowed = parent.lock().unwrap();
 
         // Настройка соединения
         parent_borrowed.reconf();
 
-        // Генерируем пакет для получения расхода
-        let mut consumption = vec![self.address, 05, 00, 01];
-        let my_crc = crc32::checksum_ieee(&consumption[..]);
-        let mut my_crc: Vec<u8> = unsafe { Vec::from_raw_parts(my_crc as *mut u8, 4, 4) };
-        consumption.append(&mut my_crc);
-
-        // Отсылаем пакет, получаем ответ и обрабатываем
-        parent_borrowed.send(&consumption);
-        let question = parent_borrowed.read();
-
-        self.processing(consumption, question);
+        // Без открытой сессии счётчик не отдаёт показания параметров
+        let open_session = self.open_session_request();
+        parent_borrowed.send(&open_session);
+        let open_session_response = parent_borrowed.read();
+        self.processing(open_session, open_session_response);
+
+        // Очередь запросов на все параметры, которые нас интересуют
+        let queue = vec![
+            ParamRequest::Consumption,
+            ParamRequest::ActiveEnergy(1),
+            ParamRequest::ActiveEnergy(2),
+            ParamRequest::ActiveEnergy(3),
+            ParamRequest::ReactiveEnergy(1),
+            ParamRequest::ReactiveEnergy(2),
+            ParamRequest::ReactiveEnergy(3),
+            ParamRequest::Voltage(1),
+            ParamRequest::Voltage(2),
+            ParamRequest::Voltage(3),
+            ParamRequest::Frequency,
+        ];
+
+        for kind in &queue {
+            let request = self.param_request(kind);
+            parent_borrowed.send(&request);
+            let response = parent_borrowed.read();
+            self.processing(request, response);
+        }
     }
 
     // Обработка ответов
     fn processing(&mut self, request: Vec<u8>, response: Vec<u8>) {
-        match (request[2], request[3]) {
-            (5, 0) => {
+        let checksum = self.checksum();
+        if !checksum.validate(&response[..]) {
+            // Кадр повреждён или это не ответ нашего счётчика — отбрасываем
+            eprintln!(
+                "Контрольная сумма не сошлась (алгоритм {:?}) — кадр отброшен",
+                checksum.mode()
+            );
+            return;
+        }
+
+        match (request[1], request[2], request[3]) {
+            (0x01, 0x01, _) => {
+                // Ответ на открытие сеанса связи, сам по себе не несёт показаний
+            }
+            (0x05, 0x00, tariff) => {
                 // Был запрос о расходе
-                let tariff = request[4];
+                if response.len() < 6 {
+                    // Кадр сошёлся по контрольной сумме, но слишком короток для расхода
+                    return;
+                }
                 let mut rdr = Cursor::new(vec![response[4], response[5], response[2], response[3]]);
                 self._consumption = rdr.read_f64::<BigEndian>().unwrap() / 1000.0;
                 println!(
@@ -231,6 +355,18 @@ impl ICounter for IMercury230 {
                     tariff, self._consumption
                 );
             }
+            (0x08, 0x11, phase) if (1..=3).contains(&phase) => {
+                self._active_energy[phase as usize - 1] = parse_fixed_point(&response);
+            }
+            (0x08, 0x12, phase) if (1..=3).contains(&phase) => {
+                self._reactive_energy[phase as usize - 1] = parse_fixed_point(&response);
+            }
+            (0x08, 0x16, selector) if (0x11..=0x13).contains(&selector) => {
+                self._voltage[(selector - 0x11) as usize] = parse_fixed_point(&response).map(|v| v as f32);
+            }
+            (0x08, 0x16, 0x40) => {
+                self._frequency = parse_fixed_point(&response).map(|v| v as i32);
+            }
             _ => (),
         }
     }
@@ -276,9 +412,14 @@ impl ICounter for IMercury230 {
     }
 
     // Вернуть канал связи
-    fn parent(&self) -> Rc<RefCell<ILinkChannel>> {
+    fn parent(&self) -> IChannelHandle {
         self._parent.clone()
     }
+
+    // Контрольная сумма, которой счётчик защищает свои кадры
+    fn checksum(&self) -> Box<dyn Checksum> {
+        Box::new(Crc16Modbus)
+    }
 }
 
 impl IElectroCounter for IMercury230 {
@@ -287,40 +428,148 @@ impl IElectroCounter for IMercury230 {
     type Voltage = f32;
 
     // Активная энергия
-    fn active_energy(&self, _phase: Self::Phase) -> Option<Self::Energy> {
-        None
+    fn active_energy(&self, phase: Self::Phase) -> Option<Self::Energy> {
+        self._active_energy.get((phase - 1) as usize).copied().flatten()
     }
 
     // Реактивная энергия
-    fn reactive_energy(&self, _phase: Self::Phase) -> Option<Self::Energy> {
-        None
+    fn reactive_energy(&self, phase: Self::Phase) -> Option<Self::Energy> {
+        self._reactive_energy.get((phase - 1) as usize).copied().flatten()
     }
 
     // Действующие значения фазных токов
-    fn voltage(&self, _phase: Self::Phase) -> Option<Self::Voltage> {
-        None
+    fn voltage(&self, phase: Self::Phase) -> Option<Self::Voltage> {
+        self._voltage.get((phase - 1) as usize).copied().flatten()
     }
 
     // Частота сети
     fn frequencies(&self, _phase: Self::Phase) -> Option<i32> {
-        None
+        self._frequency
     }
 }
 
 impl IFaceMercury230 for IMercury230 {}
 
+/// Разобрать `--async-probe COM1,COM2` из аргументов командной строки.
+fn parse_async_probe_ports() -> Option<Vec<String>> {
+    let args: Vec<String> = std::env::args().collect();
+    let idx = args.iter().position(|arg| arg == "--async-probe")?;
+    let ports = args.get(idx + 1)?;
+    let ports: Vec<String> = ports
+        .split(',')
+        .map(|s| s.trim().to_owned())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if ports.is_empty() {
+        None
+    } else {
+        Some(ports)
+    }
+}
+
+/// Опросить несколько последовательных портов параллельно вместо по очереди —
+/// полезно как быстрая диагностика перед обычным (синхронным) циклом опроса.
+fn run_async_probe(port_names: &[String]) {
+    use async_channel::{AsyncSerialChannel, IAsyncLinkChannel};
+    use tokio::sync::Mutex as AsyncMutex;
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let channels: Vec<Arc<AsyncMutex<dyn IAsyncLinkChannel + Send>>> = port_names
+        .iter()
+        .map(|name| {
+            let channel: Arc<AsyncMutex<dyn IAsyncLinkChannel + Send>> =
+                Arc::new(AsyncMutex::new(AsyncSerialChannel::with_address(name, 9600)));
+            channel
+        })
+        .collect();
+
+    let request = vec![0x01, 0x01, 0x01, 0x01];
+    let responses = runtime.block_on(async_channel::poll_concurrently(&channels, &request));
+    for (name, response) in port_names.iter().zip(responses) {
+        println!("{}: {:?}", name, response);
+    }
+}
+
 fn main() {
-    let channel = Rc::new(RefCell::new(SerialChannel::new()));
-    let counter = IMercury230::new(channel.clone());
-    let mut list = CounterList::new();
+    if let Some(ports) = parse_async_probe_ports() {
+        run_async_probe(&ports);
+        return;
+    }
+
+    let mut list = load_counter_list("config.json");
     println!("Hello!");
-    list.counters.push(Box::new(RefCell::new(counter)));
 
-    list.processing();
+    for outcome in list.processing() {
+        if let scheduler::PollOutcome::Err(reason) = outcome {
+            eprintln!("Ошибка опроса: {}", reason);
+        }
+    }
 
-    for child in &mut list.counters {
-        if let Ok(mut counter_borrowed) = child.try_borrow_mut() {
+    for child in &list.counters {
+        if let Ok(counter_borrowed) = child.lock() {
             println!("{:?}", counter_borrowed.consumption());
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NullChannel;
+
+    impl ILinkChannel for NullChannel {
+        fn new() -> Self {
+            NullChannel
+        }
+        fn reconf(&mut self) {}
+        fn send(&mut self, _data: &Vec<u8>) {}
+        fn read(&mut self) -> Vec<u8> {
+            vec![]
+        }
+    }
+
+    fn make_counter() -> IMercury230 {
+        let channel: IChannelHandle = Arc::new(Mutex::new(NullChannel::new()));
+        IMercury230::new(channel)
+    }
+
+    #[test]
+    fn voltage_round_trips_all_three_phases() {
+        let mut counter = make_counter();
+        for phase in 1..=3u8 {
+            let request = counter.param_request(&ParamRequest::Voltage(phase));
+            let raw = phase as u32 * 1000;
+            let mut response = vec![
+                request[0],
+                request[1],
+                (raw >> 16) as u8,
+                (raw >> 8) as u8,
+                raw as u8,
+            ];
+            let mut crc = counter.checksum().compute(&response[..]);
+            response.append(&mut crc);
+            counter.processing(request, response);
+        }
+
+        assert_eq!(counter.voltage(1), Some(1.0));
+        assert_eq!(counter.voltage(2), Some(2.0));
+        assert_eq!(counter.voltage(3), Some(3.0));
+    }
+
+    #[test]
+    fn short_but_validly_checksummed_frame_is_not_parsed() {
+        // Кадр короче, чем нужно для запрошенной команды (например, ответ
+        // с кодом ошибки вместо показаний), но сам по себе с верной CRC.
+        let mut counter = make_counter();
+        let request = counter.param_request(&ParamRequest::Voltage(1));
+        let mut response = vec![request[0], 0xFF];
+        let mut crc = counter.checksum().compute(&response[..]);
+        response.append(&mut crc);
+        assert_eq!(response.len(), 4);
+
+        counter.processing(request, response);
+
+        assert_eq!(counter.voltage(1), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! Планировщик параллельного опроса: группирует счётчики по каналу связи и
+//! опрашивает независимые группы на отдельных потоках.
+
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread;
+
+use crate::ICounterHandle;
+
+/// Результат опроса одного счётчика.
+pub enum PollOutcome {
+    Ok,
+    Err(String),
+}
+
+/// Подходящая по умолчанию степень параллелизма: число ядер с запасом —
+/// опрос в основном простаивает на вводе-выводе, а не грузит CPU.
+pub fn default_concurrency(overcommit_factor: usize) -> usize {
+    let cores = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    cores * overcommit_factor.max(1)
+}
+
+pub struct Scheduler;
+
+impl Scheduler {
+    /// Опросить все счётчики, раскладывая независимые группы (по каналу
+    /// связи) на рабочие потоки так, чтобы одна мёртвая шина не
+    /// останавливала опрос остальных.
+    pub fn run(counters: Vec<ICounterHandle>, concurrency: usize) -> Vec<PollOutcome> {
+        let mut groups: HashMap<usize, Vec<ICounterHandle>> = HashMap::new();
+        for counter in counters {
+            let channel_key = {
+                let guard = counter.lock().unwrap();
+                Arc::as_ptr(&guard.parent()) as *const () as usize
+            };
+            groups.entry(channel_key).or_default().push(counter);
+        }
+
+        let mut results = Vec::new();
+        let batches: Vec<Vec<ICounterHandle>> = groups.into_values().collect();
+        for batch in batches.chunks(concurrency.max(1)) {
+            let handles: Vec<_> = batch
+                .iter()
+                .cloned()
+                .map(|group| thread::spawn(move || Self::poll_group(group)))
+                .collect();
+
+            for handle in handles {
+                match handle.join() {
+                    Ok(group_results) => results.extend(group_results),
+                    Err(_) => results.push(PollOutcome::Err("worker thread panicked".to_owned())),
+                }
+            }
+        }
+        results
+    }
+
+    // Опрашиваем счётчики одной шины последовательно — они делят провод.
+    // `communicate()` ловится через `catch_unwind`, чтобы паника на одном
+    // счётчике (например, при обрыве порта) не стёрла уже собранные
+    // результаты остальных счётчиков этой же группы.
+    fn poll_group(group: Vec<ICounterHandle>) -> Vec<PollOutcome> {
+        let mut results = Vec::with_capacity(group.len());
+        for counter in group {
+            let outcome = match counter.lock() {
+                Ok(mut guard) => match catch_unwind(AssertUnwindSafe(|| guard.communicate())) {
+                    Ok(()) => PollOutcome::Ok,
+                    Err(_) => PollOutcome::Err("counter panicked during communicate()".to_owned()),
+                },
+                Err(_) => PollOutcome::Err("counter mutex poisoned".to_owned()),
+            };
+            results.push(outcome);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::{Checksum, Crc16Modbus};
+    use crate::{IChannelHandle, ICounter, ILinkChannel, IGUID};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    struct NullChannel;
+
+    impl ILinkChannel for NullChannel {
+        fn new() -> Self {
+            NullChannel
+        }
+        fn reconf(&mut self) {}
+        fn send(&mut self, _data: &Vec<u8>) {}
+        fn read(&mut self) -> Vec<u8> {
+            vec![]
+        }
+    }
+
+    /// Счётчик, который вместо реального обмена спит `delay` и пишет в общий
+    /// журнал момент начала и конца своего `communicate()` — так видно, какие
+    /// счётчики выполнялись одновременно, а какие друг за другом.
+    struct TimingCounter {
+        parent: IChannelHandle,
+        label: &'static str,
+        delay: Duration,
+        log: Arc<Mutex<Vec<(&'static str, Instant, Instant)>>>,
+        panics: bool,
+    }
+
+    impl ICounter for TimingCounter {
+        fn new(channel: IChannelHandle) -> Self {
+            TimingCounter {
+                parent: channel,
+                label: "",
+                delay: Duration::from_millis(0),
+                log: Arc::new(Mutex::new(vec![])),
+                panics: false,
+            }
+        }
+
+        fn guid(&mut self) -> IGUID {
+            String::new()
+        }
+
+        fn communicate(&mut self) {
+            let start = Instant::now();
+            thread::sleep(self.delay);
+            let end = Instant::now();
+            self.log.lock().unwrap().push((self.label, start, end));
+            if self.panics {
+                panic!("simulated dead port for {}", self.label);
+            }
+        }
+
+        fn processing(&mut self, _request: Vec<u8>, _response: Vec<u8>) {}
+
+        fn consumption(&self) -> f64 {
+            0.0
+        }
+
+        fn type_name() -> &'static str {
+            "TimingCounter"
+        }
+
+        fn name(&self) -> Option<String> {
+            None
+        }
+
+        fn serial(&self) -> Option<String> {
+            None
+        }
+
+        fn verification(&self) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn last_verification_date(&self) -> Option<Duration> {
+            None
+        }
+
+        fn verification_interval(&self) -> Option<Duration> {
+            None
+        }
+
+        fn set_verification_interval(&mut self, _interval: Duration) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn parent(&self) -> IChannelHandle {
+            self.parent.clone()
+        }
+
+        fn checksum(&self) -> Box<dyn Checksum> {
+            Box::new(Crc16Modbus)
+        }
+    }
+
+    fn make_channel() -> IChannelHandle {
+        Arc::new(std::sync::Mutex::new(NullChannel::new()))
+    }
+
+    fn make_counter(
+        parent: IChannelHandle,
+        label: &'static str,
+        delay: Duration,
+        log: Arc<Mutex<Vec<(&'static str, Instant, Instant)>>>,
+        panics: bool,
+    ) -> ICounterHandle {
+        Arc::new(Mutex::new(TimingCounter {
+            parent,
+            label,
+            delay,
+            log,
+            panics,
+        }))
+    }
+
+    #[test]
+    fn same_channel_counters_serialize_while_other_channels_run_concurrently() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let delay = Duration::from_millis(50);
+
+        let channel_a = make_channel();
+        let channel_b = make_channel();
+
+        let counters = vec![
+            make_counter(channel_a.clone(), "a1", delay, log.clone(), false),
+            make_counter(channel_a.clone(), "a2", delay, log.clone(), false),
+            make_counter(channel_b.clone(), "b1", delay, log.clone(), false),
+        ];
+
+        let started = Instant::now();
+        let results = Scheduler::run(counters, 2);
+        let elapsed = started.elapsed();
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| matches!(r, PollOutcome::Ok)));
+
+        // Два счётчика на канале A не перекрываются во времени...
+        let entries = log.lock().unwrap();
+        let a1 = entries.iter().find(|(label, ..)| *label == "a1").unwrap();
+        let a2 = entries.iter().find(|(label, ..)| *label == "a2").unwrap();
+        assert!(a1.2 <= a2.1 || a2.2 <= a1.1, "same-channel counters overlapped");
+
+        // ...но весь прогон уложился меньше чем в сумму всех трёх задержек,
+        // т.к. канал B опрашивался параллельно каналу A.
+        assert!(elapsed < delay * 3, "channels did not run concurrently: {:?}", elapsed);
+    }
+
+    #[test]
+    fn panicking_counter_does_not_erase_group_mates_outcomes() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let delay = Duration::from_millis(0);
+        let channel = make_channel();
+
+        let counters = vec![
+            make_counter(channel.clone(), "ok-before", delay, log.clone(), false),
+            make_counter(channel.clone(), "dead-port", delay, log.clone(), true),
+            make_counter(channel.clone(), "ok-after", delay, log.clone(), false),
+        ];
+
+        let results = Scheduler::run(counters, 1);
+
+        assert_eq!(results.len(), 3);
+        let ok_count = results.iter().filter(|r| matches!(r, PollOutcome::Ok)).count();
+        let err_count = results
+            .iter()
+            .filter(|r| matches!(r, PollOutcome::Err(_)))
+            .count();
+        assert_eq!(ok_count, 2);
+        assert_eq!(err_count, 1);
+    }
+}
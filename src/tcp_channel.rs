@@ -0,0 +1,83 @@
+//! Реализация `ILinkChannel` поверх TCP — для счётчиков за Ethernet-шлюзом.
+
+use std::io::prelude::*;
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::ILinkChannel;
+
+/// Канал связи со счётчиком через TCP-сокет
+pub struct TcpChannel {
+    stream: Option<TcpStream>,
+    host: String,
+    port: u16,
+    read_timeout: Duration,
+}
+
+impl TcpChannel {
+    /// Конструктор с явным адресом шлюза
+    pub fn with_address(host: &str, port: u16) -> TcpChannel {
+        TcpChannel {
+            stream: None,
+            host: host.to_owned(),
+            port,
+            read_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ILinkChannel for TcpChannel {
+    fn new() -> TcpChannel {
+        TcpChannel::with_address("127.0.0.1", 502)
+    }
+
+    fn reconf(&mut self) {
+        let stream = TcpStream::connect((self.host.as_str(), self.port)).unwrap();
+        stream.set_read_timeout(Some(self.read_timeout)).unwrap();
+        self.stream = Some(stream);
+    }
+
+    fn send(&mut self, data: &Vec<u8>) {
+        if let Some(ref mut stream) = self.stream {
+            let _ = stream.write(&data[..]).unwrap();
+        }
+    }
+
+    fn read(&mut self) -> Vec<u8> {
+        let mut result: Vec<u8> = (0..255).collect();
+
+        if let Some(ref mut stream) = self.stream {
+            let reading = stream.read(&mut result[..]).unwrap();
+            result.truncate(reading);
+        };
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn sends_and_reads_through_a_real_tcp_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4];
+            socket.read_exact(&mut request).unwrap();
+            socket.write_all(&[request[0], 0xAA, 0xBB]).unwrap();
+        });
+
+        let mut channel = TcpChannel::with_address("127.0.0.1", port);
+        channel.reconf();
+        channel.send(&vec![0x01, 0x02, 0x03, 0x04]);
+        let response = channel.read();
+
+        server.join().unwrap();
+        assert_eq!(response, vec![0x01, 0xAA, 0xBB]);
+    }
+}